@@ -1,6 +1,6 @@
 use criterion::{criterion_group, criterion_main, Criterion};
 use itertools::iproduct;
-use mls_mpm::{Mat2, Particle2D, Simulation2D, Vec2};
+use mls_mpm::{Mat2, Material, Particle2D, Simulation2D, Vec2};
 
 pub fn criterion_benchmark(c: &mut Criterion) {
     let particles = iproduct!(0..100, 0..100)
@@ -9,6 +9,11 @@ pub fn criterion_benchmark(c: &mut Criterion) {
             velocity: Vec2::new(0.5, 0.03),
             momentum: Mat2::zero(),
             mass: 1.0,
+            material: Material::Fluid,
+            deformation: Mat2::identity(),
+            volume0: 0.0,
+            age: 0.0,
+            max_lifetime: None,
         })
         .collect::<Vec<_>>();
 
@@ -0,0 +1,283 @@
+//! Optional wgpu compute-shader backend for the core APIC particle/grid transfer.
+//!
+//! Mirrors the clear/P2G/stress/grid-update/G2P phases of `Simulation2D::step`
+//! as compute passes over storage buffers, so the same simulation can scale to
+//! far larger particle counts than the CPU path. WGSL only supports atomic
+//! add on integers, so grid mass/velocity are accumulated as fixed-point
+//! `atomic<i32>` values (see `shaders/mls_mpm.wgsl`) rather than `f32`.
+//!
+//! Only the fluid EOS/viscosity stress term (MLS-MPM eq. 16) is ported to the
+//! shader. The neo-Hookean elastic stress pass and the emitter spawn/despawn
+//! bookkeeping are not, so `step_gpu` panics instead of silently running a
+//! physically different simulation if the `Simulation2D` contains any
+//! `Material::Elastic` particles, any particles with `max_lifetime` set, or
+//! any emitters; such simulations should keep using `step()`.
+
+use crate::{Material, Particle2D, Simulation2D, Vec2};
+use bytemuck::{Pod, Zeroable};
+use wgpu::util::DeviceExt;
+
+const FIXED_POINT_SCALE: f32 = 1.0e7;
+const WORKGROUP_SIZE: u32 = 64;
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Pod, Zeroable)]
+pub struct GpuParticle {
+    pub position: [f32; 2],
+    pub velocity: [f32; 2],
+    pub momentum: [f32; 4], // Mat2 columns: [m00, m01, m10, m11]
+    pub mass: f32,
+    pub _padding: [f32; 3],
+}
+
+impl From<&Particle2D> for GpuParticle {
+    fn from(particle: &Particle2D) -> Self {
+        GpuParticle {
+            position: particle.position.into(),
+            velocity: particle.velocity.into(),
+            momentum: [
+                particle.momentum.x_axis.x,
+                particle.momentum.x_axis.y,
+                particle.momentum.y_axis.x,
+                particle.momentum.y_axis.y,
+            ],
+            mass: particle.mass,
+            _padding: [0.0; 3],
+        }
+    }
+}
+
+// Field order matters here: WGSL requires `vec2<f32>` members to be 8-byte
+// aligned, so `gravity` must immediately follow the `_pad0` u32 (which ends
+// at byte 16, already 8-byte aligned) rather than sit after `dt` -- otherwise naga
+// inserts a padding word before it that this `#[repr(C)]` struct wouldn't,
+// and the uniform buffer built from `bytemuck::bytes_of` ends up smaller
+// than what the shader's `Params` struct expects.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Pod, Zeroable)]
+struct GpuParams {
+    x_size: u32,
+    y_size: u32,
+    num_particles: u32,
+    _pad0: u32,
+    gravity: [f32; 2],
+    dt: f32,
+    fixed_point_scale: f32,
+    // Weakly-compressible fluid constitutive model (MLS-MPM eq. 16), mirrors
+    // the same-named fields on `Simulation2D`.
+    eos_stiffness: f32,
+    eos_power: f32,
+    rest_density: f32,
+    dynamic_viscosity: f32,
+}
+
+/// Lazily-initialized wgpu state backing `Simulation2D::step_gpu`.
+pub struct GpuBackend {
+    device: wgpu::Device,
+    queue: wgpu::Queue,
+    bind_group: wgpu::BindGroup,
+    particle_buffer: wgpu::Buffer,
+    particle_readback_buffer: wgpu::Buffer,
+    params_buffer: wgpu::Buffer,
+    clear_grid_pipeline: wgpu::ComputePipeline,
+    p2g_pipeline: wgpu::ComputePipeline,
+    stress_pipeline: wgpu::ComputePipeline,
+    grid_update_pipeline: wgpu::ComputePipeline,
+    g2p_pipeline: wgpu::ComputePipeline,
+    num_cells: u32,
+    num_particles: u32,
+}
+
+/// Panics if `sim` relies on behavior `step_gpu` doesn't implement, so a
+/// divergence from `step()` is loud instead of silently wrong physics.
+fn assert_gpu_supported(sim: &Simulation2D) {
+    assert!(sim.emitters.is_empty(), "GPU backend does not support emitters yet; use step() instead");
+
+    assert!(
+        sim.particles.iter().all(|p| matches!(p.material, Material::Fluid) && p.max_lifetime.is_none()),
+        "GPU backend only supports Material::Fluid particles with no max_lifetime; use step() for elastic materials or particles that despawn"
+    );
+}
+
+impl GpuBackend {
+    pub fn new(sim: &Simulation2D) -> GpuBackend {
+        assert_gpu_supported(sim);
+
+        let instance = wgpu::Instance::default();
+        let adapter = pollster::block_on(instance.request_adapter(&wgpu::RequestAdapterOptions::default()))
+            .expect("no suitable wgpu adapter found");
+        let (device, queue) = pollster::block_on(adapter.request_device(&wgpu::DeviceDescriptor::default(), None))
+            .expect("failed to create wgpu device");
+
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("mls_mpm"),
+            source: wgpu::ShaderSource::Wgsl(include_str!("shaders/mls_mpm.wgsl").into()),
+        });
+
+        let num_cells = sim.num_cells as u32;
+        let num_particles = sim.particles.len() as u32;
+
+        let gpu_particles: Vec<GpuParticle> = sim.particles.iter().map(GpuParticle::from).collect();
+
+        let particle_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("particles"),
+            contents: bytemuck::cast_slice(&gpu_particles),
+            usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_SRC | wgpu::BufferUsages::COPY_DST,
+        });
+
+        let particle_readback_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("particles_readback"),
+            size: particle_buffer.size(),
+            usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+            mapped_at_creation: false,
+        });
+
+        let zero_cells = vec![0i32; num_cells as usize];
+        let grid_mass_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("grid_mass"),
+            contents: bytemuck::cast_slice(&zero_cells),
+            usage: wgpu::BufferUsages::STORAGE,
+        });
+        let grid_vel_x_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("grid_vel_x"),
+            contents: bytemuck::cast_slice(&zero_cells),
+            usage: wgpu::BufferUsages::STORAGE,
+        });
+        let grid_vel_y_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("grid_vel_y"),
+            contents: bytemuck::cast_slice(&zero_cells),
+            usage: wgpu::BufferUsages::STORAGE,
+        });
+
+        let params = GpuParams {
+            x_size: sim.x_size as u32,
+            y_size: sim.y_size as u32,
+            num_particles,
+            _pad0: 0,
+            dt: sim.dt,
+            gravity: (sim.gravity_times_dt / sim.dt).into(),
+            fixed_point_scale: FIXED_POINT_SCALE,
+            eos_stiffness: sim.eos_stiffness,
+            eos_power: sim.eos_power,
+            rest_density: sim.rest_density,
+            dynamic_viscosity: sim.dynamic_viscosity,
+        };
+        let params_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("params"),
+            contents: bytemuck::bytes_of(&params),
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+        });
+
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("mls_mpm_layout"),
+            entries: &(0..5)
+                .map(|binding| wgpu::BindGroupLayoutEntry {
+                    binding,
+                    visibility: wgpu::ShaderStages::COMPUTE,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: if binding == 4 {
+                            wgpu::BufferBindingType::Uniform
+                        } else {
+                            wgpu::BufferBindingType::Storage { read_only: false }
+                        },
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                })
+                .collect::<Vec<_>>(),
+        });
+
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("mls_mpm_bind_group"),
+            layout: &bind_group_layout,
+            entries: &[
+                wgpu::BindGroupEntry { binding: 0, resource: particle_buffer.as_entire_binding() },
+                wgpu::BindGroupEntry { binding: 1, resource: grid_mass_buffer.as_entire_binding() },
+                wgpu::BindGroupEntry { binding: 2, resource: grid_vel_x_buffer.as_entire_binding() },
+                wgpu::BindGroupEntry { binding: 3, resource: grid_vel_y_buffer.as_entire_binding() },
+                wgpu::BindGroupEntry { binding: 4, resource: params_buffer.as_entire_binding() },
+            ],
+        });
+
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("mls_mpm_pipeline_layout"),
+            bind_group_layouts: &[&bind_group_layout],
+            push_constant_ranges: &[],
+        });
+
+        let make_pipeline = |entry_point: &str| {
+            device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+                label: Some(entry_point),
+                layout: Some(&pipeline_layout),
+                module: &shader,
+                entry_point,
+            })
+        };
+
+        GpuBackend {
+            clear_grid_pipeline: make_pipeline("clear_grid"),
+            p2g_pipeline: make_pipeline("p2g"),
+            stress_pipeline: make_pipeline("stress"),
+            grid_update_pipeline: make_pipeline("grid_update"),
+            g2p_pipeline: make_pipeline("g2p"),
+            device,
+            queue,
+            bind_group,
+            particle_buffer,
+            particle_readback_buffer,
+            params_buffer,
+            num_cells,
+            num_particles,
+        }
+    }
+
+    fn dispatch(&self, pipeline: &wgpu::ComputePipeline, num_items: u32, encoder: &mut wgpu::CommandEncoder) {
+        let mut pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor::default());
+        pass.set_pipeline(pipeline);
+        pass.set_bind_group(0, &self.bind_group, &[]);
+        pass.dispatch_workgroups((num_items + WORKGROUP_SIZE - 1) / WORKGROUP_SIZE, 1, 1);
+    }
+
+    /// Runs one simulation step entirely on the GPU and reads the updated
+    /// particles back into `sim.particles`.
+    pub fn step(&mut self, sim: &mut Simulation2D) {
+        assert_gpu_supported(sim);
+
+        sim.steps_run += 1;
+
+        let mut encoder = self.device.create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
+        self.dispatch(&self.clear_grid_pipeline, self.num_cells, &mut encoder);
+        self.dispatch(&self.p2g_pipeline, self.num_particles, &mut encoder);
+        self.dispatch(&self.stress_pipeline, self.num_particles, &mut encoder);
+        self.dispatch(&self.grid_update_pipeline, self.num_cells, &mut encoder);
+        self.dispatch(&self.g2p_pipeline, self.num_particles, &mut encoder);
+        encoder.copy_buffer_to_buffer(&self.particle_buffer, 0, &self.particle_readback_buffer, 0, self.particle_buffer.size());
+        self.queue.submit(Some(encoder.finish()));
+
+        let slice = self.particle_readback_buffer.slice(..);
+        slice.map_async(wgpu::MapMode::Read, |result| result.expect("failed to map particle readback buffer"));
+        self.device.poll(wgpu::Maintain::Wait);
+
+        let gpu_particles: &[GpuParticle] = bytemuck::cast_slice(&slice.get_mapped_range());
+        for (particle, gpu_particle) in sim.particles.iter_mut().zip(gpu_particles) {
+            particle.position = Vec2::from(gpu_particle.position);
+            particle.velocity = Vec2::from(gpu_particle.velocity);
+            particle.momentum = crate::Mat2::from_cols(
+                Vec2::new(gpu_particle.momentum[0], gpu_particle.momentum[1]),
+                Vec2::new(gpu_particle.momentum[2], gpu_particle.momentum[3]),
+            );
+        }
+        self.particle_readback_buffer.unmap();
+    }
+}
+
+impl Simulation2D {
+    /// GPU-accelerated equivalent of `step()`, for `Material::Fluid`-only
+    /// simulations with no emitters (see module docs). Lazily creates its
+    /// wgpu backend on first call and reuses it afterwards.
+    pub fn step_gpu(&mut self, backend: &mut Option<GpuBackend>) {
+        let backend = backend.get_or_insert_with(|| GpuBackend::new(self));
+        backend.step(self);
+    }
+}
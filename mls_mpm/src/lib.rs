@@ -1,4 +1,18 @@
-pub use glam::{f32::Mat2, f32::Vec2, IVec2, UVec2};
+pub use glam::{f32::Mat2, f32::Mat3, f32::Vec2, f32::Vec3, IVec2, IVec3, UVec2, UVec3};
+
+#[cfg(feature = "gpu")]
+mod gpu;
+#[cfg(feature = "gpu")]
+pub use gpu::GpuBackend;
+
+#[cfg(feature = "parallel")]
+mod parallel;
+
+#[derive(Debug, Clone, Copy)]
+pub enum Material {
+    Fluid,
+    Elastic { mu: f32, lambda: f32 },
+}
 
 #[derive(Debug)]
 pub struct Particle2D {
@@ -6,6 +20,62 @@ pub struct Particle2D {
     pub velocity: Vec2,
     pub momentum: Mat2, // Affine momentum
     pub mass: f32,
+    pub material: Material,
+    pub deformation: Mat2, // Deformation gradient F, identity at rest
+    // Rest volume, lazily computed from the grid mass on the particle's first step
+    pub volume0: f32,
+    pub age: f32,                // Seconds since this particle was spawned
+    pub max_lifetime: Option<f32>, // Despawned once `age` reaches this, if set
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum SpawnRegion {
+    Point(Vec2),
+    Rect { min: Vec2, max: Vec2 },
+}
+
+/// A continuous source of particles, e.g. a fountain or hose. Accumulates
+/// `rate` against each step's `dt` so non-integer particles/sec still spawn
+/// at the right average rate.
+#[derive(Debug, Clone)]
+pub struct Emitter {
+    pub region: SpawnRegion,
+    pub rate: f32, // Particles spawned per second
+    pub velocity: Vec2,
+    pub velocity_spread: f32, // +/- random jitter added to each velocity component
+    pub mass: f32,
+    pub material: Material,
+    pub max_lifetime: Option<f32>,
+    pub accumulator: f32, // Fractional particle progress toward the next spawn
+}
+
+impl Emitter {
+    pub fn new(region: SpawnRegion, rate: f32, velocity: Vec2, velocity_spread: f32, mass: f32, material: Material, max_lifetime: Option<f32>) -> Emitter {
+        Emitter {
+            region,
+            rate,
+            velocity,
+            velocity_spread,
+            mass,
+            material,
+            max_lifetime,
+            accumulator: 0.0,
+        }
+    }
+
+    fn spawn_position(&self) -> Vec2 {
+        match self.region {
+            SpawnRegion::Point(position) => position,
+            SpawnRegion::Rect { min, max } => {
+                Vec2::new(min.x + rand::random::<f32>() * (max.x - min.x), min.y + rand::random::<f32>() * (max.y - min.y))
+            }
+        }
+    }
+
+    fn spawn_velocity(&self) -> Vec2 {
+        let jitter = Vec2::new(rand::random::<f32>() - 0.5, rand::random::<f32>() - 0.5) * (2.0 * self.velocity_spread);
+        self.velocity + jitter
+    }
 }
 
 pub struct Simulation2D {
@@ -16,6 +86,12 @@ pub struct Simulation2D {
     pub gravity_times_dt: Vec2,
     pub dt: f32,
     pub steps_run: usize,
+    // Weakly-compressible fluid constitutive model (MLS-MPM eq. 16)
+    pub eos_stiffness: f32,
+    pub eos_power: f32,
+    pub rest_density: f32,
+    pub dynamic_viscosity: f32,
+    pub emitters: Vec<Emitter>,
 }
 
 impl Simulation2D {
@@ -28,12 +104,22 @@ impl Simulation2D {
             gravity_times_dt: gravity * dt,
             dt,
             steps_run: 0,
+            eos_stiffness: 10.0,
+            eos_power: 4.0,
+            rest_density: 4.0,
+            dynamic_viscosity: 0.1,
+            emitters: Vec::new(),
         }
     }
 
     pub fn step(&mut self) {
         self.steps_run += 1;
 
+        let x_size = self.x_size;
+        let y_size = self.y_size;
+
+        spawn_from_emitters(&mut self.emitters, &mut self.particles, self.dt, x_size, y_size);
+
         // Build empty grid
         let mut grid = vec![Cell::zero(); self.num_cells];
 
@@ -70,6 +156,52 @@ impl Simulation2D {
             }
         }
 
+        // Estimate particle density/pressure from the grid mass and scatter the
+        // resulting constitutive stress (fluid or elastic, MLS-MPM eq. 16) back onto the grid.
+        for particle in self.particles.iter_mut() {
+            let cell_diff: Vec2 = particle.position - particle.position.floor() - half;
+
+            let weights: [Vec2; 3] = [
+                (half - cell_diff).powf(2.0) * 0.5f32,
+                tq - cell_diff.powf(2.0),
+                (half + cell_diff).powf(2.0) * 0.5f32,
+            ];
+
+            let cell_index = particle.position.as_i32();
+
+            let mut density = 0.0f32;
+
+            for (x_offset, x_weight) in (-1i32..=1).zip(weights.iter()) {
+                for (y_offset, y_weight) in (-1i32..=1).zip(weights.iter()) {
+                    let cell_position: UVec2 = (cell_index + IVec2::new(x_offset, y_offset)).as_u32();
+
+                    let weight = x_weight.x * y_weight.y;
+
+                    density += weight * grid[cell_position.y as usize * self.x_size + cell_position.x as usize].mass;
+                }
+            }
+
+            if particle.volume0 == 0.0 {
+                particle.volume0 = particle.mass / density;
+            }
+
+            let eq_term = particle_stress(particle, density, self.eos_stiffness, self.eos_power, self.rest_density, self.dynamic_viscosity, self.dt);
+
+            for (x_offset, x_weight) in (-1i32..=1).zip(weights.iter()) {
+                for (y_offset, y_weight) in (-1i32..=1).zip(weights.iter()) {
+                    let cell_position: UVec2 = (cell_index + IVec2::new(x_offset, y_offset)).as_u32();
+
+                    let cell_dist: Vec2 = cell_position.as_f32() - particle.position + half;
+
+                    let weight = x_weight.x * y_weight.y;
+
+                    let cell = &mut grid[cell_position.y as usize * self.x_size + cell_position.x as usize];
+
+                    cell.velocity += weight * (eq_term * cell_dist);
+                }
+            }
+        }
+
         // Update grid velocity
         for (i, cell) in grid.iter_mut().enumerate().filter(|(_, cell)| cell.mass > 0.0) {
             // Convert momentum to velocity; apply gravity
@@ -125,19 +257,275 @@ impl Simulation2D {
             }
 
             particle.momentum = b * 4.0;
+
+            // Update the deformation gradient from the gathered velocity gradient
+            particle.deformation = (Mat2::identity().add_mat2(&(particle.momentum * self.dt))).mul_mat2(&particle.deformation);
+
             particle.position += particle.velocity * self.dt; // Advect particles
+            particle.age += self.dt;
+        }
 
-            // Safety clamp to ensure particles don't exit simulation domain
-            particle.position = Vec2::new(
-                particle.position.x.clamp(1.0, self.x_size as f32 - 2.0),
-                particle.position.y.clamp(1.0, self.y_size as f32 - 2.0),
-            );
+        // Despawn particles that outlived their lifetime or left the domain
+        // (e.g. a sink past the edge of a jet); everything else is kept
+        // inside the domain via the existing safety clamp.
+        despawn_and_clamp(&mut self.particles, x_size, y_size);
+    }
+
+    /// Extracts the fluid's boundary as world-space line segments via
+    /// marching squares over the grid mass field, for rendering a continuous
+    /// surface instead of raw particle points.
+    pub fn extract_surface(&self, threshold: f32) -> Vec<(Vec2, Vec2)> {
+        let mut mass = vec![0.0f32; self.num_cells];
+
+        let half = Vec2::new(0.5, 0.5);
+        let tq = Vec2::new(0.75, 0.75);
+
+        for particle in self.particles.iter() {
+            let cell_diff: Vec2 = particle.position - particle.position.floor() - half;
+
+            let weights: [Vec2; 3] = [
+                (half - cell_diff).powf(2.0) * 0.5f32,
+                tq - cell_diff.powf(2.0),
+                (half + cell_diff).powf(2.0) * 0.5f32,
+            ];
+
+            let cell_index = particle.position.as_i32();
+
+            for (x_offset, x_weight) in (-1i32..=1).zip(weights.iter()) {
+                for (y_offset, y_weight) in (-1i32..=1).zip(weights.iter()) {
+                    let cell_position: UVec2 = (cell_index + IVec2::new(x_offset, y_offset)).as_u32();
+
+                    let mass_contrib = x_weight.x * y_weight.y * particle.mass;
+
+                    mass[cell_position.y as usize * self.x_size + cell_position.x as usize] += mass_contrib;
+                }
+            }
+        }
+
+        let mut segments = Vec::new();
+
+        for y in 0..self.y_size - 1 {
+            for x in 0..self.x_size - 1 {
+                let corners = [
+                    mass[y * self.x_size + x],
+                    mass[y * self.x_size + x + 1],
+                    mass[(y + 1) * self.x_size + x + 1],
+                    mass[(y + 1) * self.x_size + x],
+                ];
+
+                // Grid node (x, y) sits at world position (x + 0.5, y + 0.5),
+                // matching `cell_dist` in `step`.
+                let positions = [
+                    Vec2::new(x as f32 + 0.5, y as f32 + 0.5),
+                    Vec2::new(x as f32 + 1.5, y as f32 + 0.5),
+                    Vec2::new(x as f32 + 1.5, y as f32 + 1.5),
+                    Vec2::new(x as f32 + 0.5, y as f32 + 1.5),
+                ];
+
+                segments.extend(marching_squares_cell(corners, positions, threshold));
+            }
+        }
+
+        segments
+    }
+}
+
+/// Marching-squares case lookup and saddle tie-break for a single grid cell,
+/// given its four corner mass values/positions (ordered bottom-left,
+/// bottom-right, top-right, top-left) and the iso-surface threshold. Split
+/// out of `extract_surface` so the lookup table is unit-testable against
+/// known corner values without needing a full particle mass splat.
+fn marching_squares_cell(corners: [f32; 4], positions: [Vec2; 4], threshold: f32) -> Vec<(Vec2, Vec2)> {
+    let [c0, c1, c2, c3] = corners;
+    let [p0, p1, p2, p3] = positions;
+
+    let case = (c0 >= threshold) as u8
+        | (((c1 >= threshold) as u8) << 1)
+        | (((c2 >= threshold) as u8) << 2)
+        | (((c3 >= threshold) as u8) << 3);
+
+    if case == 0 || case == 15 {
+        return Vec::new();
+    }
+
+    let lerp = |a: Vec2, b: Vec2, va: f32, vb: f32| a + (b - a) * ((threshold - va) / (vb - va));
+
+    // Edges, numbered bottom/right/top/left around the cell
+    let e0 = lerp(p0, p1, c0, c1);
+    let e1 = lerp(p1, p2, c1, c2);
+    let e2 = lerp(p3, p2, c3, c2);
+    let e3 = lerp(p0, p3, c0, c3);
+
+    // Saddle tie-break for the two ambiguous cases (5 and 10):
+    // connect around the high side of the average corner value.
+    let average = (c0 + c1 + c2 + c3) * 0.25;
+
+    match case {
+        1 | 14 => vec![(e3, e0)],
+        2 | 13 => vec![(e0, e1)],
+        3 | 12 => vec![(e3, e1)],
+        4 | 11 => vec![(e1, e2)],
+        6 | 9 => vec![(e0, e2)],
+        7 | 8 => vec![(e2, e3)],
+        5 if average >= threshold => vec![(e3, e0), (e1, e2)],
+        5 => vec![(e3, e2), (e0, e1)],
+        10 if average >= threshold => vec![(e0, e1), (e2, e3)],
+        10 => vec![(e0, e3), (e1, e2)],
+        _ => unreachable!("marching squares case out of range: {}", case),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn approx_eq(a: Vec2, b: Vec2) -> bool {
+        (a - b).length() < 1e-5
+    }
+
+    fn unit_cell_positions() -> [Vec2; 4] {
+        [Vec2::new(0.0, 0.0), Vec2::new(1.0, 0.0), Vec2::new(1.0, 1.0), Vec2::new(0.0, 1.0)]
+    }
+
+    #[test]
+    fn single_corner_above_threshold_cuts_one_segment() {
+        // Only the bottom-left corner is above threshold (case 1): the
+        // surface should cut between the left edge and the bottom edge.
+        let corners = [2.0, 0.0, 0.0, 0.0];
+
+        let segments = marching_squares_cell(corners, unit_cell_positions(), 1.0);
+
+        assert_eq!(segments.len(), 1);
+        assert!(approx_eq(segments[0].0, Vec2::new(0.0, 0.5)));
+        assert!(approx_eq(segments[0].1, Vec2::new(0.5, 0.0)));
+    }
+
+    #[test]
+    fn diagonal_saddle_ties_toward_high_corners_when_average_is_high() {
+        // Opposite corners (bottom-left, top-right) above threshold is the
+        // ambiguous case 5; with the average corner value also above
+        // threshold, the two segments should wrap around the high corners.
+        let corners = [2.0, 0.0, 2.0, 0.0];
+
+        let segments = marching_squares_cell(corners, unit_cell_positions(), 1.0);
+
+        assert_eq!(segments.len(), 2);
+        assert!(approx_eq(segments[0].0, Vec2::new(0.0, 0.5)) && approx_eq(segments[0].1, Vec2::new(0.5, 0.0)));
+        assert!(approx_eq(segments[1].0, Vec2::new(1.0, 0.5)) && approx_eq(segments[1].1, Vec2::new(0.5, 1.0)));
+    }
+
+    #[test]
+    fn diagonal_saddle_ties_toward_low_corners_when_average_is_low() {
+        // Same diagonal case (5), but the average corner value is now below
+        // threshold, which should flip the tie-break to the other pairing.
+        let corners = [2.5, 0.0, 2.5, 0.0];
+
+        let segments = marching_squares_cell(corners, unit_cell_positions(), 2.0);
+
+        assert_eq!(segments.len(), 2);
+        assert!(approx_eq(segments[0].0, Vec2::new(0.0, 0.2)) && approx_eq(segments[0].1, Vec2::new(0.8, 1.0)));
+        assert!(approx_eq(segments[1].0, Vec2::new(0.2, 0.0)) && approx_eq(segments[1].1, Vec2::new(1.0, 0.8)));
+    }
+}
+
+// Keeps a position in the safe interior range `[1, size - 2]` so that
+// `cell_index ± 1` is always a valid grid index for P2G/G2P.
+pub(crate) fn clamp_to_domain(position: Vec2, x_size: usize, y_size: usize) -> Vec2 {
+    Vec2::new(position.x.clamp(1.0, x_size as f32 - 2.0), position.y.clamp(1.0, y_size as f32 - 2.0))
+}
+
+/// Spawns particles from each emitter, accumulating fractional spawns across
+/// steps so non-integer rates still average out correctly. Shared by `step`
+/// and `step_parallel` so they can't drift out of sync with each other.
+///
+/// Spawn positions are clamped into the same safe interior range as the
+/// end-of-step clamp, since a position within one cell of the boundary (e.g.
+/// a wall-mounted emitter) would otherwise make this step's P2G/G2P passes
+/// index the grid out of bounds.
+pub(crate) fn spawn_from_emitters(emitters: &mut [Emitter], particles: &mut Vec<Particle2D>, dt: f32, x_size: usize, y_size: usize) {
+    for emitter in emitters.iter_mut() {
+        emitter.accumulator += emitter.rate * dt;
+
+        while emitter.accumulator >= 1.0 {
+            emitter.accumulator -= 1.0;
+
+            particles.push(Particle2D {
+                position: clamp_to_domain(emitter.spawn_position(), x_size, y_size),
+                velocity: emitter.spawn_velocity(),
+                momentum: Mat2::zero(),
+                mass: emitter.mass,
+                material: emitter.material,
+                deformation: Mat2::identity(),
+                volume0: 0.0,
+                age: 0.0,
+                max_lifetime: emitter.max_lifetime,
+            });
+        }
+    }
+}
+
+/// Despawns particles that outlived their lifetime or left the domain (e.g.
+/// a sink past the edge of a jet); everything else is kept inside the domain
+/// via the existing safety clamp. Shared by `step` and `step_parallel`.
+pub(crate) fn despawn_and_clamp(particles: &mut Vec<Particle2D>, x_size: usize, y_size: usize) {
+    particles.retain_mut(|particle| {
+        let expired = particle.max_lifetime.map_or(false, |lifetime| particle.age >= lifetime);
+        let left_domain =
+            particle.position.x < 0.0 || particle.position.y < 0.0 || particle.position.x > x_size as f32 || particle.position.y > y_size as f32;
+
+        if expired || left_domain {
+            return false;
+        }
+
+        particle.position = clamp_to_domain(particle.position, x_size, y_size);
+
+        true
+    });
+}
+
+/// Per-particle constitutive stress term (MLS-MPM eq. 16), shared by `step`
+/// and `step_parallel`. `density` is the particle's local density, already
+/// gathered from the grid mass; the eos/viscosity parameters and `dt` are
+/// threaded through explicitly (rather than taking `&Simulation2D`) so this
+/// can be called while the simulation's particle list is already borrowed.
+pub(crate) fn particle_stress(
+    particle: &Particle2D,
+    density: f32,
+    eos_stiffness: f32,
+    eos_power: f32,
+    rest_density: f32,
+    dynamic_viscosity: f32,
+    dt: f32,
+) -> Mat2 {
+    match particle.material {
+        Material::Fluid => {
+            let volume = particle.mass / density;
+
+            let pressure = (eos_stiffness * ((density / rest_density).powf(eos_power) - 1.0)).max(-0.1);
+
+            let stress = Mat2::from_cols(Vec2::new(-pressure, 0.0), Vec2::new(0.0, -pressure));
+
+            // Viscosity: symmetrized affine momentum matrix (strain rate)
+            let strain = particle.momentum.add_mat2(&particle.momentum.transpose());
+            let stress = stress.add_mat2(&(strain * dynamic_viscosity));
+
+            stress * (-volume * 4.0 * dt)
+        }
+        Material::Elastic { mu, lambda } => {
+            // Neo-Hookean first Piola-Kirchhoff stress, avoiding an SVD
+            let f = particle.deformation;
+            let j = f.determinant();
+            let f_inv_t = f.inverse().transpose();
+
+            let p = (f.sub_mat2(&f_inv_t) * mu).add_mat2(&(f_inv_t * (lambda * j.ln())));
+
+            p.mul_mat2(&f.transpose()) * (-dt * particle.volume0 * 4.0)
         }
     }
 }
 
 #[derive(Clone)]
-struct Cell {
+pub(crate) struct Cell {
     pub velocity: Vec2,
     pub mass: f32,
 }
@@ -150,3 +538,243 @@ impl Cell {
         }
     }
 }
+
+#[derive(Debug)]
+pub struct Particle3D {
+    pub position: Vec3,
+    pub velocity: Vec3,
+    pub momentum: Mat3, // Affine momentum
+    pub mass: f32,
+}
+
+/// A 3D simulation is currently fluid-only (no elastic/emitter support yet,
+/// unlike `Simulation2D`); see the eos/viscosity fields below.
+pub struct Simulation3D {
+    pub x_size: usize,
+    pub y_size: usize,
+    pub z_size: usize,
+    pub num_cells: usize,
+    pub particles: Vec<Particle3D>,
+    pub gravity_times_dt: Vec3,
+    pub dt: f32,
+    pub steps_run: usize,
+    // Weakly-compressible fluid constitutive model (MLS-MPM eq. 16)
+    pub eos_stiffness: f32,
+    pub eos_power: f32,
+    pub rest_density: f32,
+    pub dynamic_viscosity: f32,
+}
+
+impl Simulation3D {
+    pub fn new(x_size: usize, y_size: usize, z_size: usize, particles: Vec<Particle3D>, gravity: Vec3, dt: f32) -> Simulation3D {
+        Simulation3D {
+            x_size,
+            y_size,
+            z_size,
+            num_cells: x_size * y_size * z_size,
+            particles,
+            gravity_times_dt: gravity * dt,
+            dt,
+            steps_run: 0,
+            eos_stiffness: 10.0,
+            eos_power: 4.0,
+            rest_density: 4.0,
+            dynamic_viscosity: 0.1,
+        }
+    }
+
+    pub fn step(&mut self) {
+        self.steps_run += 1;
+
+        // Build empty grid
+        let mut grid = vec![Cell3D::zero(); self.num_cells];
+
+        let half = Vec3::new(0.5, 0.5, 0.5);
+        let tq = Vec3::new(0.75, 0.75, 0.75);
+
+        // Convert particles to cell grid
+        for particle in self.particles.iter() {
+            let cell_diff: Vec3 = particle.position - particle.position.floor() - half;
+
+            let weights: [Vec3; 3] = [
+                (half - cell_diff).powf(2.0) * 0.5f32,
+                tq - cell_diff.powf(2.0),
+                (half + cell_diff).powf(2.0) * 0.5f32,
+            ];
+
+            let cell_index = particle.position.as_i32();
+
+            for (x_offset, x_weight) in (-1i32..=1).zip(weights.iter()) {
+                for (y_offset, y_weight) in (-1i32..=1).zip(weights.iter()) {
+                    for (z_offset, z_weight) in (-1i32..=1).zip(weights.iter()) {
+                        let cell_position: UVec3 = (cell_index + IVec3::new(x_offset, y_offset, z_offset)).as_u32();
+
+                        let cell_dist: Vec3 = cell_position.as_f32() - particle.position + half;
+
+                        let q = particle.momentum * cell_dist;
+
+                        let cell = &mut grid[self.cell_grid_index(cell_position)];
+
+                        let mass_contrib = x_weight.x * y_weight.y * z_weight.z * particle.mass;
+
+                        cell.mass += mass_contrib;
+                        cell.velocity += (particle.velocity + q) * mass_contrib;
+                    }
+                }
+            }
+        }
+
+        // Estimate particle density/pressure from the grid mass and scatter the
+        // resulting fluid constitutive stress (MLS-MPM eq. 16) back onto the grid,
+        // same as `Simulation2D::step` — without this every particle free-falls
+        // and compresses with nothing resisting it.
+        for particle in self.particles.iter() {
+            let cell_diff: Vec3 = particle.position - particle.position.floor() - half;
+
+            let weights: [Vec3; 3] = [
+                (half - cell_diff).powf(2.0) * 0.5f32,
+                tq - cell_diff.powf(2.0),
+                (half + cell_diff).powf(2.0) * 0.5f32,
+            ];
+
+            let cell_index = particle.position.as_i32();
+
+            let mut density = 0.0f32;
+
+            for (x_offset, x_weight) in (-1i32..=1).zip(weights.iter()) {
+                for (y_offset, y_weight) in (-1i32..=1).zip(weights.iter()) {
+                    for (z_offset, z_weight) in (-1i32..=1).zip(weights.iter()) {
+                        let cell_position: UVec3 = (cell_index + IVec3::new(x_offset, y_offset, z_offset)).as_u32();
+
+                        let weight = x_weight.x * y_weight.y * z_weight.z;
+
+                        density += weight * grid[self.cell_grid_index(cell_position)].mass;
+                    }
+                }
+            }
+
+            let volume = particle.mass / density;
+
+            let pressure = (self.eos_stiffness * ((density / self.rest_density).powf(self.eos_power) - 1.0)).max(-0.1);
+
+            let stress = Mat3::from_cols(Vec3::new(-pressure, 0.0, 0.0), Vec3::new(0.0, -pressure, 0.0), Vec3::new(0.0, 0.0, -pressure));
+
+            // Viscosity: symmetrized affine momentum matrix (strain rate)
+            let strain = particle.momentum.add_mat3(&particle.momentum.transpose());
+            let stress = stress.add_mat3(&(strain * self.dynamic_viscosity));
+
+            let eq_term = stress * (-volume * 4.0 * self.dt);
+
+            for (x_offset, x_weight) in (-1i32..=1).zip(weights.iter()) {
+                for (y_offset, y_weight) in (-1i32..=1).zip(weights.iter()) {
+                    for (z_offset, z_weight) in (-1i32..=1).zip(weights.iter()) {
+                        let cell_position: UVec3 = (cell_index + IVec3::new(x_offset, y_offset, z_offset)).as_u32();
+
+                        let cell_dist: Vec3 = cell_position.as_f32() - particle.position + half;
+
+                        let weight = x_weight.x * y_weight.y * z_weight.z;
+
+                        let cell = &mut grid[self.cell_grid_index(cell_position)];
+
+                        cell.velocity += weight * (eq_term * cell_dist);
+                    }
+                }
+            }
+        }
+
+        // Update grid velocity
+        for (i, cell) in grid.iter_mut().enumerate().filter(|(_, cell)| cell.mass > 0.0) {
+            // Convert momentum to velocity; apply gravity
+            cell.velocity /= cell.mass;
+            cell.velocity += self.gravity_times_dt;
+
+            // Boundary conditions
+            let x = i % self.x_size;
+            let y = (i / self.x_size) % self.y_size;
+            let z = i / (self.x_size * self.y_size);
+
+            if x < 2 || x > self.x_size - 3 {
+                cell.velocity.x = 0.0;
+            }
+
+            if y < 2 || y > self.y_size - 3 {
+                cell.velocity.y = 0.0;
+            }
+
+            if z < 2 || z > self.z_size - 3 {
+                cell.velocity.z = 0.0;
+            }
+        }
+
+        // Convert cell grid back to particles
+        for particle in self.particles.iter_mut() {
+            particle.velocity = Vec3::zero();
+
+            let cell_diff: Vec3 = particle.position - particle.position.floor() - half;
+
+            let weights: [Vec3; 3] = [
+                (half - cell_diff).powf(2.0) * 0.5f32,
+                tq - cell_diff.powf(2.0),
+                (half + cell_diff).powf(2.0) * 0.5f32,
+            ];
+
+            let cell_index = particle.position.as_i32();
+
+            let mut b = Mat3::zero();
+
+            for (x_offset, x_weight) in (-1i32..=1).zip(weights.iter()) {
+                for (y_offset, y_weight) in (-1i32..=1).zip(weights.iter()) {
+                    for (z_offset, z_weight) in (-1i32..=1).zip(weights.iter()) {
+                        let weight = x_weight.x * y_weight.y * z_weight.z;
+
+                        let cell_position: UVec3 = (cell_index + IVec3::new(x_offset, y_offset, z_offset)).as_u32();
+
+                        let cell_dist: Vec3 = cell_position.as_f32() - particle.position + half;
+
+                        let weighted_velocity: Vec3 = grid[self.cell_grid_index(cell_position)].velocity * weight;
+
+                        // APIC paper equation 10, constructing inner term for B
+                        let term = Mat3::from_cols(
+                            weighted_velocity * cell_dist.x,
+                            weighted_velocity * cell_dist.y,
+                            weighted_velocity * cell_dist.z,
+                        );
+
+                        b = b.add_mat3(&term);
+
+                        particle.velocity += weighted_velocity;
+                    }
+                }
+            }
+
+            particle.momentum = b * 4.0;
+            particle.position += particle.velocity * self.dt; // Advect particles
+
+            // Safety clamp to ensure particles don't exit simulation domain
+            particle.position = Vec3::new(
+                particle.position.x.clamp(1.0, self.x_size as f32 - 2.0),
+                particle.position.y.clamp(1.0, self.y_size as f32 - 2.0),
+                particle.position.z.clamp(1.0, self.z_size as f32 - 2.0),
+            );
+        }
+    }
+
+    fn cell_grid_index(&self, cell_position: UVec3) -> usize {
+        cell_position.z as usize * self.x_size * self.y_size + cell_position.y as usize * self.x_size + cell_position.x as usize
+    }
+}
+
+#[derive(Clone)]
+struct Cell3D {
+    pub velocity: Vec3,
+    pub mass: f32,
+}
+
+impl Cell3D {
+    pub fn zero() -> Cell3D {
+        Cell3D {
+            velocity: Vec3::zero(),
+            mass: 0.0,
+        }
+    }
+}
@@ -9,7 +9,7 @@ use kiss3d::{
     text::Font,
     window::{State, Window},
 };
-use mls_mpm::{Mat2, Particle2D, Simulation2D, Vec2};
+use mls_mpm::{Mat2, Material, Particle2D, Simulation2D, Vec2};
 use nalgebra::{Matrix4, Point2, Point3};
 use std::time::Instant;
 
@@ -19,7 +19,8 @@ struct AppState {
     camera: FixedView,
     frames: usize,
     zero_frame_time: Instant,
-    last_known_fps: u32
+    last_known_fps: u32,
+    surface_threshold: f32,
 }
 
 impl State for AppState {
@@ -44,6 +45,13 @@ impl State for AppState {
             data.push(Point3::new(1.0, 1.0, 1.0));
         }
 
+        let to_screen = |p: Vec2| Point3::new(-(p.x - 16.0), p.y - 16.0, 40.0);
+        let surface_color = Point3::new(0.3, 0.6, 1.0);
+
+        for (start, end) in self.simulation.extract_surface(self.surface_threshold) {
+            window.draw_line(&to_screen(start), &to_screen(end), &surface_color);
+        }
+
         self.simulation.step();
 
         if self.frames % 50 == 0 {
@@ -79,6 +87,11 @@ fn main() {
                     velocity: Vec2::new(20.0 * (rng.gen::<f32>() - 0.5), 20.0 * (rng.gen::<f32>() - 0.5)),
                     momentum: Mat2::zero(),
                     mass: 1.0,
+                    material: Material::Fluid,
+                    deformation: Mat2::identity(),
+                    volume0: 0.0,
+                    age: 0.0,
+                    max_lifetime: None,
                 })
                 .collect::<Vec<_>>(),
             Vec2::new(0.0, -0.1),
@@ -87,7 +100,8 @@ fn main() {
         camera: FixedView::new(),
         frames: 0,
         zero_frame_time: Instant::now(),
-        last_known_fps: 0
+        last_known_fps: 0,
+        surface_threshold: 2.0,
     };
 
     window.render_loop(app)